@@ -0,0 +1,14 @@
+// Optional TOML config file, merged under whatever CLI flags were given
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct FileConfig {
+    pub name: Option<String>,
+    pub count: Option<u64>,
+    pub interval_secs: Option<u64>,
+}
+
+pub fn load(path: &str) -> Result<FileConfig, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&text)?)
+}
@@ -1,28 +1,121 @@
+//! Two independent rendezvous paths coexist here, matching ping-server:
+//!
+//! - Without `--rendezvous` (just `--server`, optionally with `--relay`):
+//!   the server this client dials only ever plays the rendezvous *server*
+//!   role and never registers any peer under `RENDEZVOUS_NAMESPACE` (it
+//!   can't register itself — see the server's module doc comment for why).
+//!   So the `Discover`/`Discovered` round-trip below never actually
+//!   surfaces new peer records; `registrations` is always empty, and
+//!   `Discovered` is used only to confirm the server side of the connection
+//!   is alive before attempting the Kameo lookup. `--server` is mandatory
+//!   in this mode — this client has no other way to learn the server's
+//!   address.
+//! - With `--rendezvous <broker-addr>`: this client instead dials a
+//!   standalone rendezvous broker (see the `rendezvous-server` crate) and
+//!   `discover()`s against *it*. Because the broker isn't the peer being
+//!   registered, `ping-server --rendezvous <broker-addr>` can actually
+//!   register there, so this discover() call returns a real peer record —
+//!   this client dials the PingActor host it points to instead of needing
+//!   `--server` at all.
+
+mod config;
+mod metrics;
+
 use kameo::prelude::*;
 use kameo::remote;
 use libp2p::{
-    noise, tcp, yamux,
-    swarm::{NetworkBehaviour, SwarmEvent},
-    Multiaddr,
+    dcutr, identify, metrics::Metrics as Libp2pMetrics, multiaddr::Protocol, noise, relay,
+    rendezvous, tcp, yamux,
+    swarm::{dial_opts::DialOpts, NetworkBehaviour, SwarmEvent},
+    Multiaddr, PeerId,
 };
+use metrics::AppMetrics;
+use prometheus_client::registry::Registry;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{info, warn, error};
 use tracing_subscriber::EnvFilter;
 use clap::Parser;
 use futures::StreamExt;
+use tokio::sync::oneshot;
+
+// Namespace the post-connect rendezvous discover() runs under: an
+// always-empty liveness handshake against the server, or a real lookup
+// against the broker with --rendezvous; see the module doc comment above
+const RENDEZVOUS_NAMESPACE: &str = "kameo-ping";
+
+// Message UUID and REMOTE_ID must stay in sync with the server's copies of
+// the same constants; a mismatch is caught at startup via identify instead
+// of surfacing as a silent discovery failure
+const PING_MESSAGE_UUID: &str = "a1b2c3d4-e5f6-7890-abcd-ef1234567890";
+const PING_ACTOR_REMOTE_ID: &str = "ping_pong_app::PingActor";
+
+const DEFAULT_REGISTRATION_NAME: &str = "ping_actor";
+const DEFAULT_PING_COUNT: u64 = 100;
+const DEFAULT_INTERVAL_SECS: u64 = 2;
 
 // Command-line arguments for server address
 #[derive(Parser, Debug)]
 #[command(name = "ping-client")]
 struct Args {
+    /// Server multiaddr to dial directly, e.g. /ip4/.../tcp/36341/p2p/<peer-id>.
+    /// Required unless --rendezvous is given instead, in which case the
+    /// server's address is discovered via the broker and this is ignored
     #[arg(short, long)]
     server: Option<String>,
+
+    /// Transport to build the swarm with
+    #[arg(short, long, value_enum, default_value_t = Transport::Tcp)]
+    transport: Transport,
+
+    /// Relay multiaddr to dial through when the server is behind a NAT.
+    /// Only used in --server mode; ignored when --rendezvous is set
+    #[arg(long)]
+    relay: Option<String>,
+
+    /// Rendezvous broker multiaddr (run `rendezvous-server`) to discover the
+    /// PingActor host's address from, instead of being handed it via
+    /// --server. Must include a /p2p/<peer-id> suffix.
+    #[arg(long)]
+    rendezvous: Option<String>,
+
+    /// Address to serve Prometheus/OpenMetrics text on, e.g. 127.0.0.1:9091
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Name of the PingActor to look up, overriding the config file and the built-in default
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Number of pings to send, overriding the config file and the built-in default
+    #[arg(long)]
+    count: Option<u64>,
+
+    /// Seconds to wait between pings, overriding the config file and the built-in default
+    #[arg(long)]
+    interval: Option<u64>,
+
+    /// Optional TOML config file supplying name/count/interval (CLI flags take priority)
+    #[arg(long)]
+    config: Option<String>,
+}
+
+// Transport selection shared in spirit with the server's `--transport` flag
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Transport {
+    Tcp,
+    Quic,
 }
 
 #[derive(NetworkBehaviour)]
 struct MyBehaviour {
     kameo: remote::Behaviour,
+    identify: identify::Behaviour,
+    rendezvous: rendezvous::client::Behaviour,
+    relay_client: relay::client::Behaviour,
+    dcutr: dcutr::Behaviour,
 }
 
 #[derive(Actor)]
@@ -32,7 +125,7 @@ pub struct PingActor {
 
 // REMOTE_ID must match server for actor discovery
 impl RemoteActor for PingActor {
-    const REMOTE_ID: &'static str = "ping_pong_app::PingActor";
+    const REMOTE_ID: &'static str = PING_ACTOR_REMOTE_ID;
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -48,7 +141,7 @@ pub struct Pong {
     total_pings: u64,
 }
 
-// UUID must match server
+// Must match PING_MESSAGE_UUID above and the server's copy of both constants
 #[remote_message("a1b2c3d4-e5f6-7890-abcd-ef1234567890")]
 impl Message<Ping> for PingActor {
     type Reply = Pong;
@@ -68,76 +161,459 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!(" Starting Ping Client with custom swarm...");
 
-    if let Some(server_addr) = args.server {
-        info!(" Using custom swarm configuration for direct connection");
-        info!(" Server address: {}", server_addr);
-        
-        let server_multiaddr: Multiaddr = server_addr.parse()?;
-        
-        // Build custom swarm with same configuration as server
-        let mut swarm = libp2p::SwarmBuilder::with_new_identity()
-            .with_tokio()
-            .with_tcp(tcp::Config::default(), noise::Config::new, || yamux::Config::default())?
-            .with_behaviour(|key| {
-                let peer_id = key.public().to_peer_id();
-                let messaging_config = remote::messaging::Config::default()
-                    .with_request_timeout(Duration::from_secs(120));
-                let kameo = remote::Behaviour::new(peer_id, messaging_config);
-                Ok(MyBehaviour { kameo })
-            })?
-            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(600)))
-            .build();
+    // The broker's peer id, required via a /p2p/<peer-id> suffix on
+    // --rendezvous — needed upfront to recognise its ConnectionEstablished
+    // and to issue discover() against it
+    let rendezvous_broker_peer_id = args
+        .rendezvous
+        .as_ref()
+        .map(|addr| {
+            let multiaddr: Multiaddr = addr.parse()?;
+            multiaddr
+                .iter()
+                .find_map(|protocol| match protocol {
+                    Protocol::P2p(peer_id) => Some(peer_id),
+                    _ => None,
+                })
+                .ok_or_else(|| format!("--rendezvous '{}' has no /p2p/<peer-id> suffix", multiaddr).into())
+        })
+        .transpose()
+        .map_err(|e: Box<dyn std::error::Error>| e)?;
+    let using_broker = rendezvous_broker_peer_id.is_some();
+
+    if args.server.is_none() && args.rendezvous.is_none() {
+        info!(" No server address or rendezvous broker provided. Use --server or --rendezvous.");
+        return Ok(());
+    }
+    if using_broker && args.server.is_some() {
+        info!(" --server is ignored when --rendezvous is set; the PingActor host's address will be discovered via the broker");
+    }
+    if using_broker && args.relay.is_some() {
+        info!(" --relay is ignored when --rendezvous is set; the discovered PingActor host is dialed directly");
+    }
+
+    {
+        info!(" Transport: {:?}", args.transport);
+
+        let server_multiaddr: Option<Multiaddr> = if using_broker {
+            None
+        } else {
+            info!(" Using custom swarm configuration for direct connection");
+            let server_addr = args.server.clone().expect("validated above: --server required without --rendezvous");
+            info!(" Server address: {}", server_addr);
+            Some(server_addr.parse()?)
+        };
+
+        // Merge config file values underneath whatever CLI flags were given
+        let file_config = match &args.config {
+            Some(path) => config::load(path)?,
+            None => config::FileConfig::default(),
+        };
+        let registration_name = args
+            .name
+            .or(file_config.name)
+            .unwrap_or_else(|| DEFAULT_REGISTRATION_NAME.to_string());
+        let ping_count = args.count.or(file_config.count).unwrap_or(DEFAULT_PING_COUNT);
+        let interval_secs = args
+            .interval
+            .or(file_config.interval_secs)
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+
+        fn build_behaviour(
+            key: &libp2p::identity::Keypair,
+            relay_client: relay::client::Behaviour,
+        ) -> Result<MyBehaviour, Box<dyn std::error::Error + Send + Sync>> {
+            let peer_id = key.public().to_peer_id();
+            let messaging_config = remote::messaging::Config::default()
+                .with_request_timeout(Duration::from_secs(120));
+            let kameo = remote::Behaviour::new(peer_id, messaging_config);
+            let identify = identify::Behaviour::new(identify::Config::new(
+                format!("/kameo-ping/{}/{}", PING_ACTOR_REMOTE_ID, PING_MESSAGE_UUID),
+                key.public(),
+            ));
+            let rendezvous = rendezvous::client::Behaviour::new(key.clone());
+            let dcutr = dcutr::Behaviour::new(peer_id);
+            Ok(MyBehaviour { kameo, identify, rendezvous, relay_client, dcutr })
+        }
+
+        // Build custom swarm with same configuration as server. QUIC folds
+        // encryption and stream multiplexing into one handshake, cutting
+        // connection-setup latency for the lookup+ask path below. The relay
+        // client transport is wired in either way so we can reach a server
+        // behind a NAT via `--relay`.
+        let mut swarm = match args.transport {
+            Transport::Tcp => libp2p::SwarmBuilder::with_new_identity()
+                .with_tokio()
+                .with_tcp(tcp::Config::default(), noise::Config::new, || yamux::Config::default())?
+                .with_relay_client(noise::Config::new, yamux::Config::default)?
+                .with_behaviour(build_behaviour)?
+                .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(600)))
+                .build(),
+            Transport::Quic => libp2p::SwarmBuilder::with_new_identity()
+                .with_tokio()
+                .with_quic()
+                .with_relay_client(noise::Config::new, yamux::Config::default)?
+                .with_behaviour(build_behaviour)?
+                .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(600)))
+                .build(),
+        };
 
         swarm.behaviour().kameo.init_global();
 
         info!(" Client Peer ID: {}", swarm.local_peer_id());
 
-        // Listen on any available port
-        swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+        // Wire up swarm-level libp2p metrics alongside the ping-pong metrics
+        // recorded around the ask() calls below
+        let mut registry = Registry::default();
+        let libp2p_metrics = Libp2pMetrics::new(&mut registry);
+        let app_metrics = AppMetrics::new(&mut registry);
 
-        info!(" Dialing server at {}...", server_multiaddr);
-        swarm.dial(server_multiaddr.clone())?;
+        if let Some(metrics_addr) = args.metrics_addr {
+            let registry = Arc::new(registry);
+            tokio::spawn(metrics::serve(metrics_addr, registry));
+        }
+
+        // Listen on any available port, using the multiaddr scheme the
+        // selected transport expects
+        let listen_addr: Multiaddr = match args.transport {
+            Transport::Tcp => "/ip4/0.0.0.0/tcp/0".parse()?,
+            Transport::Quic => "/ip4/0.0.0.0/udp/0/quic-v1".parse()?,
+        };
+        swarm.listen_on(listen_addr)?;
+
+        // The server's peer id, when --server spells it out via a
+        // /p2p/<peer-id> suffix. Used below to tell the real server's
+        // `ConnectionEstablished` apart from the relay's own — required
+        // when --relay is set (the circuit dial target has no destination
+        // otherwise), and used opportunistically to gate the rendezvous
+        // liveness handshake even without a relay in play. Unused in broker
+        // mode, where the server's peer id isn't known until the broker's
+        // `Discovered` event resolves it.
+        let server_peer_id_hint = server_multiaddr.as_ref().and_then(|addr| {
+            addr.iter().find_map(|protocol| match protocol {
+                Protocol::P2p(peer_id) => Some(peer_id),
+                _ => None,
+            })
+        });
+
+        // Whether a relay is in play, recorded before `args.relay` is moved
+        // out below — used to tell the relay's own `ConnectionEstablished`/
+        // identify info apart from the real server's further down
+        let relay_given = args.relay.is_some();
+
+        // Reach the server through a relay circuit when it isn't directly
+        // dialable, otherwise fall back to dialing it directly as before.
+        // In broker mode there's nothing to dial yet — the broker is dialed
+        // below instead, and the server's address only becomes known once
+        // the broker's `Discovered` event resolves it.
+        let dial_target: Option<Multiaddr> = if using_broker {
+            None
+        } else if let Some(relay_addr) = args.relay {
+            let server_multiaddr = server_multiaddr
+                .clone()
+                .expect("validated above: --server required without --rendezvous");
+            let server_peer_id = server_peer_id_hint.ok_or_else(|| {
+                format!(
+                    "--server '{}' has no /p2p/<peer-id> suffix, required when combined with --relay",
+                    server_multiaddr
+                )
+            })?;
+
+            let relay_multiaddr: Multiaddr = relay_addr.parse()?;
+            info!(" Dialing relay at {}...", relay_multiaddr);
+            swarm.dial(relay_multiaddr.clone())?;
+            let own_circuit_addr = relay_multiaddr.clone().with(Protocol::P2pCircuit);
+            swarm.listen_on(own_circuit_addr.clone())?;
+
+            // Confirm our own circuit reservation as externally reachable so
+            // DCUtR has an address to hole-punch against on our side too
+            swarm.add_external_address(own_circuit_addr);
+
+            Some(
+                relay_multiaddr
+                    .with(Protocol::P2pCircuit)
+                    .with(Protocol::P2p(server_peer_id)),
+            )
+        } else {
+            server_multiaddr.clone()
+        };
+
+        if using_broker {
+            let broker_addr = args
+                .rendezvous
+                .as_ref()
+                .expect("validated above: --rendezvous required for broker mode");
+            let broker_multiaddr: Multiaddr = broker_addr.parse()?;
+            info!(" Dialing rendezvous broker at {}...", broker_multiaddr);
+            swarm.dial(broker_multiaddr)?;
+        } else {
+            let dial_target =
+                dial_target.expect("dial_target is Some whenever using_broker is false");
+            info!(" Dialing server at {}...", dial_target);
+            swarm.dial(dial_target)?;
+        }
+
+        // Fires Ok once the server's identify info matches this client's
+        // UUID/REMOTE_ID and, without --rendezvous, the rendezvous liveness
+        // handshake has completed too; fires Err on an identify mismatch or
+        // a failed dial or discovery. Signals that it's safe to attempt the
+        // Kameo lookup — or that startup must fail outright — instead of
+        // guessing with a sleep
+        let (ready_tx, ready_rx) = oneshot::channel::<Result<(), String>>();
+        let mut ready_tx = Some(ready_tx);
 
         // Run swarm event loop in background task
+        let loop_metrics = app_metrics.clone();
         let swarm_handle = tokio::spawn(async move {
+            // Set once the broker's `Discovered` event resolves the real
+            // PingActor host's peer id; unused outside broker mode
+            let mut resolved_server_peer_id: Option<PeerId> = None;
+            // Outside broker mode ready needs both, in whichever order they land
+            let mut server_identified = false;
+            let mut liveness_done = false;
+
             loop {
-                match swarm.select_next_some().await {
+                let event = swarm.select_next_some().await;
+                libp2p_metrics.record(&event);
+                match event {
                     SwarmEvent::Behaviour(MyBehaviourEvent::Kameo(event)) => {
                         info!(" Kameo event: {:?}", event);
                     }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Identify(event)) => {
+                        if let identify::Event::Received { peer_id, info, .. } = &event {
+                            // Neither the broker nor (with --relay) the relay's own
+                            // connection runs this ping protocol, so their identify
+                            // info is never expected to match — only check the peer
+                            // that's meant to actually be running PingActor
+                            let is_relay_peer = !using_broker
+                                && relay_given
+                                && server_peer_id_hint.map_or(false, |expected| expected != *peer_id);
+                            if Some(*peer_id) != rendezvous_broker_peer_id && !is_relay_peer {
+                                let expected = format!(
+                                    "/kameo-ping/{}/{}",
+                                    PING_ACTOR_REMOTE_ID, PING_MESSAGE_UUID
+                                );
+                                if info.protocol_version != expected {
+                                    let msg = format!(
+                                        "Server {} reports protocol '{}', expected '{}' — its UUID/REMOTE_ID is out of sync with this client, Kameo discovery would fail silently",
+                                        peer_id, info.protocol_version, expected
+                                    );
+                                    tracing::error!(" {}", msg);
+
+                                    // A mismatch is fatal for this run: drop the
+                                    // connection and fail startup instead of
+                                    // limping on to a Kameo lookup that can only
+                                    // ever time out
+                                    let _ = swarm.disconnect_peer_id(*peer_id);
+                                    if let Some(tx) = ready_tx.take() {
+                                        let _ = tx.send(Err(msg));
+                                    }
+                                } else {
+                                    server_identified = true;
+                                    if liveness_done || (using_broker && Some(*peer_id) == resolved_server_peer_id) {
+                                        if let Some(tx) = ready_tx.take() {
+                                            let _ = tx.send(Ok(()));
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        info!(" Identify event: {:?}", event);
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Rendezvous(
+                        rendezvous::client::Event::Discovered { registrations, .. },
+                    )) => {
+                        if using_broker {
+                            match registrations.first() {
+                                Some(registration) => {
+                                    let server_peer_id = registration.record.peer_id();
+                                    info!(
+                                        " Discovered PingActor host {} via rendezvous broker under namespace '{}'",
+                                        server_peer_id, RENDEZVOUS_NAMESPACE
+                                    );
+                                    for address in &registration.record.addresses() {
+                                        swarm.add_peer_address(server_peer_id, address.clone());
+                                    }
+                                    resolved_server_peer_id = Some(server_peer_id);
+                                    if let Err(e) = swarm.dial(DialOpts::peer_id(server_peer_id).build()) {
+                                        let msg = format!("failed to dial discovered PingActor host {}: {}", server_peer_id, e);
+                                        tracing::error!(" {}", msg);
+                                        if let Some(tx) = ready_tx.take() {
+                                            let _ = tx.send(Err(msg));
+                                        }
+                                    }
+                                }
+                                None => {
+                                    let msg = format!(
+                                        "rendezvous broker has no PingActor host registered under namespace '{}' yet",
+                                        RENDEZVOUS_NAMESPACE
+                                    );
+                                    tracing::error!(" {}", msg);
+                                    if let Some(tx) = ready_tx.take() {
+                                        let _ = tx.send(Err(msg));
+                                    }
+                                }
+                            }
+                        } else {
+                            info!(
+                                " Rendezvous liveness handshake complete under namespace '{}' ({} registration(s) — expected 0, see module doc comment)",
+                                RENDEZVOUS_NAMESPACE,
+                                registrations.len()
+                            );
+                            for registration in &registrations {
+                                for address in &registration.record.addresses() {
+                                    swarm.add_peer_address(registration.record.peer_id(), address.clone());
+                                }
+                            }
+                            liveness_done = true;
+                            if server_identified {
+                                if let Some(tx) = ready_tx.take() {
+                                    let _ = tx.send(Ok(()));
+                                }
+                            }
+                        }
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Rendezvous(
+                        rendezvous::client::Event::DiscoverFailed { error, .. },
+                    )) => {
+                        let msg = if using_broker {
+                            format!("rendezvous broker discovery failed: {:?}", error)
+                        } else {
+                            format!("rendezvous liveness handshake failed: {:?}", error)
+                        };
+                        tracing::error!(" {}", msg);
+                        if let Some(tx) = ready_tx.take() {
+                            let _ = tx.send(Err(msg));
+                        }
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Rendezvous(event)) => {
+                        info!(" Rendezvous event: {:?}", event);
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Dcutr(event)) => {
+                        match event.result {
+                            Ok(_) => info!(" DCUtR hole punch to {} succeeded, traffic can migrate to the direct connection", event.remote_peer_id),
+                            Err(e) => warn!(" DCUtR hole punch to {} failed: {}", event.remote_peer_id, e),
+                        }
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::RelayClient(event)) => {
+                        info!(" Relay client event: {:?}", event);
+                    }
                     SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
                         info!(" Connected to {} via {}", peer_id, endpoint.get_remote_address());
-                        
-                        // Add server peer to address book for Kademlia DHT
+
+                        // Add peer to address book for rendezvous/Kameo discovery
                         let remote_addr = endpoint.get_remote_address().clone();
                         swarm.add_peer_address(peer_id, remote_addr.clone());
-                        info!(" Added server to swarm: {} at {}", peer_id, remote_addr);
+                        info!(" Added peer to swarm: {} at {}", peer_id, remote_addr);
+
+                        // Whether discover() should be issued against this peer —
+                        // true for the broker itself (broker mode) or the server
+                        // peer (legacy mode); false for the relay and for the
+                        // already-resolved server connection in broker mode, which
+                        // needs no second discover() call — its identify info
+                        // signals ready instead
+                        let should_discover = if using_broker {
+                            if Some(peer_id) == rendezvous_broker_peer_id {
+                                info!(
+                                    " Asking rendezvous broker who is registered under namespace '{}'...",
+                                    RENDEZVOUS_NAMESPACE
+                                );
+                                true
+                            } else {
+                                if Some(peer_id) == resolved_server_peer_id {
+                                    info!(" Connected to discovered PingActor host {}", peer_id);
+                                }
+                                false
+                            }
+                        } else {
+                            // With --relay, this fires once for the relay connection and
+                            // again for the circuit connection to the actual server —
+                            // only the latter runs rendezvous::server::Behaviour, so gate
+                            // on the resolved server peer id instead of discovering
+                            // against whichever peer happened to connect first (the
+                            // relay would only ever answer with DiscoverFailed)
+                            let is_server_peer = server_peer_id_hint.map_or(true, |expected| expected == peer_id);
+                            if !is_server_peer {
+                                info!(" Connected to relay {}, not the server — skipping rendezvous discover against it", peer_id);
+                            }
+                            is_server_peer
+                        };
+
+                        if should_discover {
+                            // Ask the rendezvous point (the broker with --rendezvous, the
+                            // server otherwise) who is registered under our namespace
+                            swarm.behaviour_mut().rendezvous.discover(
+                                Some(rendezvous::Namespace::from_static(RENDEZVOUS_NAMESPACE)),
+                                None,
+                                None,
+                                peer_id,
+                            );
+                        }
+
+                        // Neither the relay nor the rendezvous broker (when set) is a
+                        // ping peer, so exclude them from the client-facing counter,
+                        // matching ping-server's equivalent metric
+                        let is_relay_peer = !using_broker
+                            && relay_given
+                            && server_peer_id_hint.map_or(false, |expected| expected != peer_id);
+                        if Some(peer_id) != rendezvous_broker_peer_id && !is_relay_peer {
+                            loop_metrics.connected_peers.inc();
+                        }
                     }
                     SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
                         warn!(" Connection to {} closed: {:?}", peer_id, cause);
+
+                        let is_relay_peer = !using_broker
+                            && relay_given
+                            && server_peer_id_hint.map_or(false, |expected| expected != peer_id);
+                        if Some(peer_id) != rendezvous_broker_peer_id && !is_relay_peer {
+                            loop_metrics.connected_peers.dec();
+                        }
                     }
                     SwarmEvent::NewListenAddr { address, .. } => {
                         info!(" Listening on {}", address);
                     }
                     SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
                         warn!(" Failed to connect to {:?}: {}", peer_id, error);
+
+                        // Nothing else will ever drive discovery forward for
+                        // this run if the dial itself never succeeds — fail
+                        // fast instead of leaving `ready_rx.await` hanging
+                        if let Some(tx) = ready_tx.take() {
+                            let _ = tx.send(Err(format!(
+                                "failed to connect to {:?}: {}",
+                                peer_id, error
+                            )));
+                        }
                     }
                     _ => {}
                 }
             }
         });
 
-        // Wait for DHT to synchronize routing tables
-        info!("⏳ Waiting for DHT to stabilize (15 seconds)...");
-        tokio::time::sleep(Duration::from_secs(15)).await;
+        // Wait for the rendezvous round-trip instead of sleeping blind — or
+        // fail fast if identify caught a UUID/REMOTE_ID mismatch
+        if using_broker {
+            info!("⏳ Waiting for the PingActor host to be discovered via the rendezvous broker...");
+        } else {
+            info!("⏳ Waiting for rendezvous liveness handshake under namespace '{}'...", RENDEZVOUS_NAMESPACE);
+        }
+        if let Err(msg) = ready_rx.await? {
+            swarm_handle.abort();
+            return Err(msg.into());
+        }
 
-        // Lookup remote PingActor via Kademlia DHT with retries
-        info!("🔍 Searching for remote PingActor...");
-        let mut retry_count = 0;
+        // Lookup the remote PingActor now that discovery has confirmed the
+        // server side of the connection is up. That only proves the
+        // connection and protocol handshake are live, not that the server's
+        // background task has finished spawning and registering the actor
+        // yet, so keep a bounded retry around the lookup itself rather than
+        // hard-failing on the first miss.
+        info!("🔍 Searching for remote PingActor '{}'...", registration_name);
         let max_retries = 10;
-        
+        let mut retry_count = 0;
         let remote_actor = loop {
-            match RemoteActorRef::<PingActor>::lookup("ping_actor").await? {
+            match RemoteActorRef::<PingActor>::lookup(&registration_name).await? {
                 Some(actor) => {
                     info!(" Found remote PingActor!");
                     break actor;
@@ -145,7 +621,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 None => {
                     retry_count += 1;
                     if retry_count >= max_retries {
-                        error!(" Failed to find PingActor after {} attempts", max_retries);
+                        error!(" PingActor not found after {} attempts", max_retries);
                         return Ok(());
                     }
                     warn!("⏳ PingActor not found yet, retrying... (attempt {}/{})", retry_count, max_retries);
@@ -154,39 +630,44 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         };
 
-        info!(" Starting ping-pong sequence...");
+        info!(" Starting ping-pong sequence ({} pings, {}s apart)...", ping_count, interval_secs);
         let start = Instant::now();
 
-        // Send 100 ping messages with 2-second intervals
-        for i in 1..=100 {
+        for i in 1..=ping_count {
             let ping = Ping {
                 message: format!("Hello from client, ping #{}", i),
                 sequence: i,
             };
 
             info!(" Sending PING #{}", i);
+            app_metrics.pings_sent.inc();
 
+            let ask_start = Instant::now();
             match remote_actor.ask(&ping).await {
                 Ok(pong) => {
+                    app_metrics.ping_rtt_seconds.observe(ask_start.elapsed().as_secs_f64());
+                    app_metrics.pongs_received.inc();
                     info!(" Received PONG #{} (total: {})", pong.sequence, pong.total_pings);
                 }
                 Err(e) => {
+                    app_metrics.ping_failures.inc();
                     error!(" Failed: {}", e);
                 }
             }
 
-            if i < 100 {
-                tokio::time::sleep(Duration::from_secs(2)).await;
+            if i < ping_count {
+                tokio::time::sleep(Duration::from_secs(interval_secs)).await;
             }
         }
 
         let duration = start.elapsed();
-        info!(" Done! Total: {:?}, Avg: {:?}", duration, duration / 100);
+        if ping_count > 0 {
+            info!(" Done! Total: {:?}, Avg: {:?}", duration, duration / ping_count as u32);
+        } else {
+            info!(" Done! Total: {:?} (--count 0, no pings sent)", duration);
+        }
 
         swarm_handle.abort();
-        
-    } else {
-        info!(" No server address provided. Use --server flag.");
     }
 
     Ok(())
@@ -0,0 +1,106 @@
+// Application-level metrics for the ping-pong loop, exposed alongside
+// libp2p's own swarm metrics on a small OpenMetrics/Prometheus HTTP endpoint.
+use prometheus_client::encoding::text::encode;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::registry::Registry;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+#[derive(Clone)]
+pub struct AppMetrics {
+    pub ping_rtt_seconds: Histogram,
+    pub pings_sent: Counter,
+    pub pongs_received: Counter,
+    pub ping_failures: Counter,
+    pub connected_peers: Gauge,
+}
+
+impl AppMetrics {
+    pub fn new(registry: &mut Registry) -> Self {
+        let ping_rtt_seconds = Histogram::new(
+            [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0].into_iter(),
+        );
+        let pings_sent = Counter::default();
+        let pongs_received = Counter::default();
+        let ping_failures = Counter::default();
+        let connected_peers = Gauge::default();
+
+        registry.register(
+            "ping_rtt_seconds",
+            "Round-trip time of an ask(Ping) -> Pong exchange",
+            ping_rtt_seconds.clone(),
+        );
+        registry.register("pings_sent", "Total ping messages sent", pings_sent.clone());
+        registry.register(
+            "pongs_received",
+            "Total pong replies received",
+            pongs_received.clone(),
+        );
+        registry.register(
+            "ping_failures",
+            "Total ask(Ping) calls that errored",
+            ping_failures.clone(),
+        );
+        registry.register(
+            "connected_peers",
+            "Number of currently connected ping peers (relay and rendezvous broker excluded)",
+            connected_peers.clone(),
+        );
+
+        Self {
+            ping_rtt_seconds,
+            pings_sent,
+            pongs_received,
+            ping_failures,
+            connected_peers,
+        }
+    }
+}
+
+// Serves the registry as OpenMetrics text on `GET /metrics` until the process exits
+pub async fn serve(addr: SocketAddr, registry: Arc<Registry>) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(" Failed to bind metrics endpoint on {}: {}", addr, e);
+            return;
+        }
+    };
+
+    info!(" Serving metrics on http://{}/metrics", addr);
+
+    loop {
+        let (mut stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!(" Metrics listener accept error: {}", e);
+                continue;
+            }
+        };
+
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            if stream.read(&mut buf).await.is_err() {
+                return;
+            }
+
+            let mut body = String::new();
+            if encode(&mut body, &registry).is_err() {
+                return;
+            }
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
@@ -0,0 +1,13 @@
+// Optional TOML config file, merged under whatever CLI flags were given
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct FileConfig {
+    pub port: Option<u16>,
+    pub name: Option<String>,
+}
+
+pub fn load(path: &str) -> Result<FileConfig, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&text)?)
+}
@@ -1,21 +1,133 @@
+//! Two independent rendezvous paths coexist here:
+//!
+//! - Without `--rendezvous`: this binary's own `rendezvous::server::Behaviour`
+//!   (see `MyBehaviour::rendezvous` below) never registers any peer —
+//!   including itself — under `RENDEZVOUS_NAMESPACE`, since libp2p refuses to
+//!   dial your own `PeerId` (`DialError::LocalPeerId`). The client's
+//!   `discover()` call against this server therefore always returns an empty
+//!   registration table; it exists to confirm the rendezvous round-trip
+//!   works before the client attempts the Kameo lookup, not to hand out this
+//!   server's address. Clients still need to be told how to reach us out of
+//!   band, via `--server` or `--relay` directly.
+//! - With `--rendezvous <broker-addr>`: this binary also runs
+//!   `MyBehaviour::rendezvous_client` and registers the PingActor host under
+//!   `RENDEZVOUS_NAMESPACE` with that *separate* process (see the
+//!   `rendezvous-server` crate), once listening. A `ping-client --rendezvous
+//!   <broker-addr>` then gets real peer records back from its own
+//!   `discover()` call against the broker, without ever being told our
+//!   address directly — because the broker, unlike us, isn't the peer being
+//!   registered, so it can actually serve as a rendezvous point for us.
+
+mod config;
+mod metrics;
+
 use kameo::prelude::*;
 use kameo::remote;
 use libp2p::{
-    noise, tcp, yamux,
-    swarm::{NetworkBehaviour, SwarmEvent, dial_opts::DialOpts},
+    dcutr, identify, metrics::Metrics as Libp2pMetrics, noise, relay, rendezvous, tcp, webrtc,
+    yamux,
+    swarm::{NetworkBehaviour, SwarmEvent},
     Multiaddr,
 };
+use metrics::AppMetrics;
+use prometheus_client::registry::Registry;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
+use clap::Parser;
 use futures::StreamExt;
 
+// Namespace used for both rendezvous paths described in the module doc
+// comment above: the always-empty liveness handshake against this binary's
+// own rendezvous server, and the real broker registration with --rendezvous
+const RENDEZVOUS_NAMESPACE: &str = "kameo-ping";
+
+// Message UUID and REMOTE_ID must stay in sync with the client's copies of
+// the same constants below; a mismatch is caught at startup via identify
+// instead of surfacing as a silent discovery failure
+const PING_MESSAGE_UUID: &str = "a1b2c3d4-e5f6-7890-abcd-ef1234567890";
+const PING_ACTOR_REMOTE_ID: &str = "ping_pong_app::PingActor";
+
+const DEFAULT_PORT: u16 = 36341;
+const DEFAULT_REGISTRATION_NAME: &str = "ping_actor";
+
+// Command-line arguments for the server
+#[derive(Parser, Debug)]
+#[command(name = "ping-server")]
+struct Args {
+    /// Transport to build the swarm with
+    #[arg(short, long, value_enum, default_value_t = Transport::Tcp)]
+    transport: Transport,
+
+    /// Relay multiaddr to make a circuit reservation on, for reachability
+    /// when this server itself sits behind a NAT
+    #[arg(long)]
+    relay: Option<String>,
+
+    /// Rendezvous broker multiaddr (run `rendezvous-server`) to register the
+    /// PingActor host under RENDEZVOUS_NAMESPACE with, once listening. Must
+    /// include a /p2p/<peer-id> suffix. Unlike this binary's own built-in
+    /// rendezvous server (which can't register itself), a separate broker
+    /// lets ping-client discover our address instead of being handed it
+    /// via --server/--relay.
+    #[arg(long)]
+    rendezvous: Option<String>,
+
+    /// Address to serve Prometheus/OpenMetrics text on, e.g. 127.0.0.1:9090
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Also listen for WebRTC connections so browser clients can reach the PingActor
+    #[arg(long)]
+    webrtc: bool,
+
+    /// Port to listen on, overriding the config file and the built-in default
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Name the PingActor registers under, overriding the config file and the built-in default
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Optional TOML config file supplying port/name (CLI flags take priority)
+    #[arg(long)]
+    config: Option<String>,
+}
+
+// Port the optional WebRTC listener binds, alongside the TCP/QUIC one
+const WEBRTC_PORT: u16 = 36342;
+
+// Transport selection shared in spirit with the client's `--transport` flag
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Transport {
+    Tcp,
+    Quic,
+}
+
 // Custom network behaviour wrapping Kameo's remote behaviour
 // Required for custom swarm configuration
 #[derive(NetworkBehaviour)]
 struct MyBehaviour {
     kameo: remote::Behaviour,
+    identify: identify::Behaviour,
+    // This process only ever plays the rendezvous *server* role: libp2p's
+    // connection pool refuses to dial your own PeerId (`DialError::LocalPeerId`),
+    // so there is no way for this swarm to also act as a rendezvous *client*
+    // of itself in order to appear in its own registration table. Clients
+    // learn our address the same way they dial us — directly, or through a
+    // relay circuit — and use `discover()` against us purely as a liveness
+    // round-trip; see the client's `Discovered` handling.
+    rendezvous: rendezvous::server::Behaviour,
+    // Only exercised when --rendezvous is given: registers the PingActor
+    // host with an external broker (see the module doc comment above).
+    // Otherwise stays idle — it's never dialed against itself or anyone
+    // else, so it never registers or discovers anything.
+    rendezvous_client: rendezvous::client::Behaviour,
+    relay_client: relay::client::Behaviour,
+    dcutr: dcutr::Behaviour,
 }
 
 // PingActor maintains a count of received pings
@@ -26,7 +138,7 @@ pub struct PingActor {
 
 // REMOTE_ID must match between client and server for discovery
 impl RemoteActor for PingActor {
-    const REMOTE_ID: &'static str = "ping_pong_app::PingActor";
+    const REMOTE_ID: &'static str = PING_ACTOR_REMOTE_ID;
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -42,7 +154,7 @@ pub struct Pong {
     total_pings: u64,
 }
 
-// UUID must match between client and server for proper message routing
+// Must match PING_MESSAGE_UUID above and the client's copy of both constants
 #[remote_message("a1b2c3d4-e5f6-7890-abcd-ef1234567890")]
 impl Message<Ping> for PingActor {
     type Reply = Pong;
@@ -64,41 +176,198 @@ impl Message<Ping> for PingActor {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
         .init();
 
     info!(" Starting Ping Server with custom swarm...");
+    info!(" Transport: {:?}", args.transport);
+
+    // Merge config file values underneath whatever CLI flags were given
+    let file_config = match &args.config {
+        Some(path) => config::load(path)?,
+        None => config::FileConfig::default(),
+    };
+    let port = args.port.or(file_config.port).unwrap_or(DEFAULT_PORT);
+    let registration_name = args
+        .name
+        .or(file_config.name)
+        .unwrap_or_else(|| DEFAULT_REGISTRATION_NAME.to_string());
+
+    fn build_behaviour(
+        key: &libp2p::identity::Keypair,
+        relay_client: relay::client::Behaviour,
+    ) -> Result<MyBehaviour, Box<dyn std::error::Error + Send + Sync>> {
+        let peer_id = key.public().to_peer_id();
+        let messaging_config = remote::messaging::Config::default()
+            .with_request_timeout(Duration::from_secs(120));
+        let kameo = remote::Behaviour::new(peer_id, messaging_config);
+        let identify = identify::Behaviour::new(identify::Config::new(
+            format!("/kameo-ping/{}/{}", PING_ACTOR_REMOTE_ID, PING_MESSAGE_UUID),
+            key.public(),
+        ));
+        let rendezvous =
+            rendezvous::server::Behaviour::new(rendezvous::server::Config::default());
+        let rendezvous_client = rendezvous::client::Behaviour::new(key.clone());
+        let dcutr = dcutr::Behaviour::new(peer_id);
+        Ok(MyBehaviour {
+            kameo,
+            identify,
+            rendezvous,
+            rendezvous_client,
+            relay_client,
+            dcutr,
+        })
+    }
 
-    // Build custom libp2p swarm with TCP, noise encryption, and yamux multiplexing
-    let mut swarm = libp2p::SwarmBuilder::with_new_identity()
-        .with_tokio()
-        .with_tcp(tcp::Config::default(), noise::Config::new, || yamux::Config::default())?
-        .with_behaviour(|key| {
-            let peer_id = key.public().to_peer_id();
-            let messaging_config = remote::messaging::Config::default()
-                .with_request_timeout(Duration::from_secs(120));
-            let kameo = remote::Behaviour::new(peer_id, messaging_config);
-            Ok(MyBehaviour { kameo })
-        })?
-        .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(600)))
-        .build();
+    // A fresh self-signed certificate each run is fine here: the example
+    // only needs the WebRTC handshake to succeed, not a stable fingerprint.
+    // Boxed into StreamMuxerBox so its Output unifies with the TCP/QUIC legs
+    // already boxed the same way internally, as `with_other_transport` requires.
+    fn build_webrtc_transport(
+        key: &libp2p::identity::Keypair,
+    ) -> Result<
+        libp2p::core::transport::Boxed<(libp2p::PeerId, libp2p::core::muxing::StreamMuxerBox)>,
+        Box<dyn std::error::Error + Send + Sync>,
+    > {
+        // Scoped locally rather than at module level: this module already
+        // defines its own `enum Transport` for the `--transport` CLI flag,
+        // and a module-level `use libp2p::Transport` would collide with it
+        // (E0255). Only this function needs the trait, for `.map`/`.boxed`.
+        use libp2p::Transport as _;
+
+        let cert = webrtc::tokio::Certificate::generate(&mut rand::thread_rng())?;
+        let transport = webrtc::tokio::Transport::new(key.clone(), cert)
+            .map(|(peer_id, conn), _| (peer_id, libp2p::core::muxing::StreamMuxerBox::new(conn)))
+            .boxed();
+        Ok(transport)
+    }
+
+    // Build custom libp2p swarm, folding encryption and multiplexing into a
+    // single QUIC handshake when selected, or TCP+noise+yamux otherwise.
+    // The relay client transport is wired in either way so this server can
+    // make a circuit reservation when it is itself behind a NAT. The WebRTC
+    // transport is always registered too; it's only actually listened on
+    // when `--webrtc` is passed, so native TCP/QUIC clients are unaffected.
+    let mut swarm = match args.transport {
+        Transport::Tcp => libp2p::SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(tcp::Config::default(), noise::Config::new, || yamux::Config::default())?
+            .with_relay_client(noise::Config::new, yamux::Config::default)?
+            .with_other_transport(build_webrtc_transport)?
+            .with_behaviour(build_behaviour)?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(600)))
+            .build(),
+        Transport::Quic => libp2p::SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_quic()
+            .with_relay_client(noise::Config::new, yamux::Config::default)?
+            .with_other_transport(build_webrtc_transport)?
+            .with_behaviour(build_behaviour)?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(600)))
+            .build(),
+    };
 
     // Initialize Kameo's global actor registry
     swarm.behaviour().kameo.init_global();
 
-    let peer_id = *swarm.local_peer_id();
-    info!(" Server Peer ID: {}", peer_id);
+    let local_peer_id = *swarm.local_peer_id();
+    info!(" Server Peer ID: {}", local_peer_id);
+
+    // Wire up swarm-level libp2p metrics alongside the connection-lifecycle
+    // metrics recorded from the event loop below
+    let mut registry = Registry::default();
+    let libp2p_metrics = Libp2pMetrics::new(&mut registry);
+    let app_metrics = AppMetrics::new(&mut registry);
+
+    if let Some(metrics_addr) = args.metrics_addr {
+        let registry = Arc::new(registry);
+        tokio::spawn(metrics::serve(metrics_addr, registry));
+    }
 
-    // Listen on all interfaces on port 36341
-    swarm.listen_on("/ip4/0.0.0.0/tcp/36341".parse()?)?;
+    // Listen on all interfaces on the configured port, using the multiaddr
+    // scheme the selected transport expects
+    let listen_addr: Multiaddr = match args.transport {
+        Transport::Tcp => format!("/ip4/0.0.0.0/tcp/{}", port).parse()?,
+        Transport::Quic => format!("/ip4/0.0.0.0/udp/{}/quic-v1", port).parse()?,
+    };
+    swarm.listen_on(listen_addr)?;
+
+    // Keep the existing TCP/QUIC listener active while adding a WebRTC one,
+    // so browser clients (which can't open raw TCP) and native clients coexist
+    if args.webrtc {
+        let webrtc_addr: Multiaddr = format!("/ip4/0.0.0.0/udp/{}/webrtc-direct", WEBRTC_PORT).parse()?;
+        swarm.listen_on(webrtc_addr)?;
+    }
+
+    // If a relay was given, dial it and make a circuit reservation so peers
+    // behind other NATs can still reach us through it. Tracked so the event
+    // loop below can tell this connection apart from actual ping clients —
+    // it isn't one, and shouldn't count as one in the connection metrics.
+    let mut relay_peer_id = None;
+    if let Some(relay_addr) = args.relay {
+        let relay_multiaddr: Multiaddr = relay_addr.parse()?;
+        relay_peer_id = relay_multiaddr.iter().find_map(|protocol| match protocol {
+            libp2p::multiaddr::Protocol::P2p(peer_id) => Some(peer_id),
+            _ => None,
+        });
+        info!(" Dialing relay at {}...", relay_multiaddr);
+        swarm.dial(relay_multiaddr.clone())?;
+        let circuit_addr = relay_multiaddr.with(libp2p::multiaddr::Protocol::P2pCircuit);
+        swarm.listen_on(circuit_addr.clone())?;
+
+        // Confirm the circuit address as externally reachable so identify
+        // advertises it to peers and DCUtR has an address to hole-punch
+        // against; nothing else derives this for us now that registering it
+        // under the rendezvous namespace isn't something a server can do for
+        // itself (see `MyBehaviour::rendezvous`'s doc comment)
+        swarm.add_external_address(circuit_addr);
+    }
+
+    // If a rendezvous broker was given, dial it now; registration itself is
+    // deferred to the broker's ConnectionEstablished event below, since
+    // rendezvous::client::Behaviour::register requires an active connection
+    let rendezvous_broker_peer_id = match &args.rendezvous {
+        Some(rendezvous_addr) => {
+            let rendezvous_multiaddr: Multiaddr = rendezvous_addr.parse()?;
+            let broker_peer_id = rendezvous_multiaddr
+                .iter()
+                .find_map(|protocol| match protocol {
+                    libp2p::multiaddr::Protocol::P2p(peer_id) => Some(peer_id),
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "--rendezvous '{}' has no /p2p/<peer-id> suffix",
+                        rendezvous_multiaddr
+                    )
+                })?;
+            info!(" Dialing rendezvous broker at {}...", rendezvous_multiaddr);
+            swarm.dial(rendezvous_multiaddr)?;
+            Some(broker_peer_id)
+        }
+        None => None,
+    };
+
+    info!(
+        " Serving rendezvous liveness handshakes under namespace '{}' — on its own this does not make the PingActor discoverable; without --rendezvous, clients still need our address via --server/--relay",
+        RENDEZVOUS_NAMESPACE
+    );
+    if rendezvous_broker_peer_id.is_some() {
+        info!(
+            " Will register the PingActor host under namespace '{}' with the rendezvous broker once listening, for real ping-client discovery",
+            RENDEZVOUS_NAMESPACE
+        );
+    }
 
     // Spawn and register the PingActor in background task to avoid blocking swarm
     tokio::spawn(async move {
         tokio::time::sleep(Duration::from_millis(100)).await;
         let ping_actor = PingActor::spawn(PingActor { ping_count: 0 });
-        match ping_actor.register("ping_actor").await {
-            Ok(_) => info!(" PingActor registered and ready!"),
+        match ping_actor.register(&registration_name).await {
+            Ok(_) => info!(" PingActor registered as '{}' and ready!", registration_name),
             Err(e) => info!(" Failed to register actor: {}", e),
         }
     });
@@ -109,27 +378,121 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     loop {
         tokio::select! {
             event = swarm.select_next_some() => {
+                libp2p_metrics.record(&event);
                 match event {
                     SwarmEvent::Behaviour(MyBehaviourEvent::Kameo(event)) => {
                         info!(" Kameo event: {:?}", event);
                     }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Identify(event)) => {
+                        if let identify::Event::Received { peer_id, info, .. } = &event {
+                            // Neither the relay nor the rendezvous broker (when set) runs
+                            // this ping protocol, so their identify info never matches
+                            // `expected` below — that's expected, not a misconfiguration
+                            if Some(*peer_id) != relay_peer_id && Some(*peer_id) != rendezvous_broker_peer_id {
+                                let expected = format!(
+                                    "/kameo-ping/{}/{}",
+                                    PING_ACTOR_REMOTE_ID, PING_MESSAGE_UUID
+                                );
+                                if info.protocol_version != expected {
+                                    tracing::error!(
+                                        " Peer {} reports protocol '{}', expected '{}' — its UUID/REMOTE_ID is out of sync with this server, closing the connection instead of relying on Kameo discovery for it",
+                                        peer_id, info.protocol_version, expected
+                                    );
+                                    let _ = swarm.disconnect_peer_id(*peer_id);
+                                }
+                            }
+                        }
+                        info!(" Identify event: {:?}", event);
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Rendezvous(event)) => {
+                        info!(" Rendezvous server event: {:?}", event);
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::RendezvousClient(event)) => {
+                        match &event {
+                            rendezvous::client::Event::Registered { namespace, .. } => {
+                                info!(" Registered PingActor host under namespace '{}' with rendezvous broker", namespace);
+                            }
+                            rendezvous::client::Event::RegisterFailed { namespace, error, .. } => {
+                                tracing::error!(" Failed to register under namespace '{}' with rendezvous broker: {:?}", namespace, error);
+                            }
+                            _ => {}
+                        }
+                        info!(" Rendezvous client event: {:?}", event);
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Dcutr(event)) => {
+                        match event.result {
+                            Ok(_) => info!(" DCUtR hole punch to {} succeeded", event.remote_peer_id),
+                            Err(e) => warn!(" DCUtR hole punch to {} failed: {}", event.remote_peer_id, e),
+                        }
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::RelayClient(event)) => {
+                        info!(" Relay client event: {:?}", event);
+                    }
                     SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
                         info!(" Client connected: {} via {}", peer_id, endpoint.get_remote_address());
-                        
-                        // Add peer to address book for Kademlia DHT discovery
+
+                        // Add peer to address book for rendezvous/Kameo discovery
                         let remote_addr = endpoint.get_remote_address().clone();
                         swarm.add_peer_address(peer_id, remote_addr.clone());
                         info!(" Added peer address to swarm: {} at {}", peer_id, remote_addr);
+
+                        // Register with the broker now that we're connected to it;
+                        // deferred from startup because register() needs a live
+                        // connection to send the request over
+                        if Some(peer_id) == rendezvous_broker_peer_id {
+                            swarm.behaviour_mut().rendezvous_client.register(
+                                rendezvous::Namespace::from_static(RENDEZVOUS_NAMESPACE),
+                                peer_id,
+                                None,
+                            );
+                        }
+
+                        // Neither the relay nor the rendezvous broker (when set) is
+                        // a ping client and shouldn't inflate the client-facing counters
+                        if Some(peer_id) != relay_peer_id && Some(peer_id) != rendezvous_broker_peer_id {
+                            app_metrics.connections_established.inc();
+                            app_metrics.connected_peers.inc();
+                        }
                     }
                     SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
                         info!(" Client disconnected: {} ({:?})", peer_id, cause);
+
+                        if Some(peer_id) != relay_peer_id && Some(peer_id) != rendezvous_broker_peer_id {
+                            app_metrics.connections_closed.inc();
+                            app_metrics.connected_peers.dec();
+                        }
                     }
                     SwarmEvent::NewListenAddr { address, .. } => {
                         info!(" Listening on {}", address);
-                        let addr_string = address.to_string();
-                        let addr_parts: Vec<&str> = addr_string.split('/').collect();
-                        if addr_parts.len() >= 3 {
-                            info!(" Connection address: /ip4/{}/tcp/36341/p2p/{}", addr_parts[2], peer_id);
+
+                        // Only the TCP/QUIC listener on our own --transport/port
+                        // actually matches the tcp-or-quic shape built below;
+                        // relay circuit reservations and the WebRTC listener
+                        // carry their own protocol stacks and would get a
+                        // bogus hint if stamped out the same way
+                        let is_selected_transport_addr = address.iter().any(|protocol| {
+                            match (args.transport, protocol) {
+                                (Transport::Tcp, libp2p::multiaddr::Protocol::Tcp(p)) => p == port,
+                                (Transport::Quic, libp2p::multiaddr::Protocol::Udp(p)) => p == port,
+                                _ => false,
+                            }
+                        });
+                        if is_selected_transport_addr {
+                            // Confirm our own direct listen address as externally
+                            // reachable so a rendezvous broker registration (if
+                            // --rendezvous is set) has a real address to advertise,
+                            // the same way the relay circuit address is confirmed above
+                            swarm.add_external_address(address.clone());
+
+                            let addr_string = address.to_string();
+                            let addr_parts: Vec<&str> = addr_string.split('/').collect();
+                            if addr_parts.len() >= 3 {
+                                let hint = match args.transport {
+                                    Transport::Tcp => format!("/ip4/{}/tcp/{}/p2p/{}", addr_parts[2], port, local_peer_id),
+                                    Transport::Quic => format!("/ip4/{}/udp/{}/quic-v1/p2p/{}", addr_parts[2], port, local_peer_id),
+                                };
+                                info!(" Connection address: {}", hint);
+                            }
                         }
                     }
                     SwarmEvent::IncomingConnection { .. } => {
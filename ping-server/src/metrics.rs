@@ -0,0 +1,90 @@
+// Application-level metrics for the server's connection lifecycle, exposed
+// alongside libp2p's own swarm metrics on a small OpenMetrics/Prometheus HTTP endpoint.
+use prometheus_client::encoding::text::encode;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+pub struct AppMetrics {
+    pub connections_established: Counter,
+    pub connections_closed: Counter,
+    pub connected_peers: Gauge,
+}
+
+impl AppMetrics {
+    pub fn new(registry: &mut Registry) -> Self {
+        let connections_established = Counter::default();
+        let connections_closed = Counter::default();
+        let connected_peers = Gauge::default();
+
+        registry.register(
+            "connections_established",
+            "Total inbound connections established",
+            connections_established.clone(),
+        );
+        registry.register(
+            "connections_closed",
+            "Total connections closed",
+            connections_closed.clone(),
+        );
+        registry.register(
+            "connected_peers",
+            "Number of currently connected ping peers (relay and rendezvous broker excluded)",
+            connected_peers.clone(),
+        );
+
+        Self {
+            connections_established,
+            connections_closed,
+            connected_peers,
+        }
+    }
+}
+
+// Serves the registry as OpenMetrics text on `GET /metrics` until the process exits
+pub async fn serve(addr: SocketAddr, registry: Arc<Registry>) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(" Failed to bind metrics endpoint on {}: {}", addr, e);
+            return;
+        }
+    };
+
+    info!(" Serving metrics on http://{}/metrics", addr);
+
+    loop {
+        let (mut stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!(" Metrics listener accept error: {}", e);
+                continue;
+            }
+        };
+
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            if stream.read(&mut buf).await.is_err() {
+                return;
+            }
+
+            let mut body = String::new();
+            if encode(&mut body, &registry).is_err() {
+                return;
+            }
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
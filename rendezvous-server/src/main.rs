@@ -0,0 +1,157 @@
+//! Standalone rendezvous broker for real PingActor discovery.
+//!
+//! `ping-server`'s own built-in `rendezvous::server::Behaviour` can never
+//! register the PingActor host itself — libp2p refuses to dial your own
+//! `PeerId` (`DialError::LocalPeerId`), so a node can't act as both the
+//! rendezvous server and a rendezvous client of itself. That leaves
+//! `ping-client`'s `discover()` against the server as a liveness round-trip
+//! only; it never returns real peer records, and `ping-server`/`ping-client`
+//! still need an out-of-band address via `--server`/`--relay`.
+//!
+//! This binary is the independent third party that makes real discovery
+//! possible: it only ever plays the rendezvous *server* role, for a
+//! PingActor host (`ping-server --rendezvous <this-address>`) that
+//! registers with it as a rendezvous *client* after listening, and for
+//! `ping-client --rendezvous <this-address>`, which discovers that
+//! registration here instead of being handed the server's address directly.
+
+use clap::Parser;
+use futures::StreamExt;
+use libp2p::{
+    identify, noise, rendezvous, tcp, yamux,
+    swarm::{NetworkBehaviour, SwarmEvent},
+    Multiaddr,
+};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_PORT: u16 = 36343;
+
+// Advertised via identify purely so this broker is recognisable in
+// ping-server/ping-client logs; they never compare it against their protocol
+// version (they skip the broker by peer id), since this binary speaks no
+// ping-pong protocol of its own
+const BROKER_PROTOCOL_VERSION: &str = "/kameo-ping/rendezvous-broker/1.0.0";
+
+// Command-line arguments for the rendezvous broker
+#[derive(Parser, Debug)]
+#[command(name = "rendezvous-server")]
+struct Args {
+    /// Transport to build the swarm with
+    #[arg(short, long, value_enum, default_value_t = Transport::Tcp)]
+    transport: Transport,
+
+    /// Port to listen on, overriding the built-in default
+    #[arg(long)]
+    port: Option<u16>,
+}
+
+// Transport selection shared in spirit with ping-server/ping-client's
+// `--transport` flag
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Transport {
+    Tcp,
+    Quic,
+}
+
+// This process exists purely to run the rendezvous server role for peers
+// that can't register themselves; no Kameo/ping behaviour needed here
+#[derive(NetworkBehaviour)]
+struct MyBehaviour {
+    identify: identify::Behaviour,
+    rendezvous: rendezvous::server::Behaviour,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+
+    info!(" Starting Rendezvous Broker...");
+    info!(" Transport: {:?}", args.transport);
+
+    let port = args.port.unwrap_or(DEFAULT_PORT);
+
+    fn build_behaviour(
+        key: &libp2p::identity::Keypair,
+    ) -> Result<MyBehaviour, Box<dyn std::error::Error + Send + Sync>> {
+        let identify = identify::Behaviour::new(identify::Config::new(
+            BROKER_PROTOCOL_VERSION.to_string(),
+            key.public(),
+        ));
+        let rendezvous =
+            rendezvous::server::Behaviour::new(rendezvous::server::Config::default());
+        Ok(MyBehaviour { identify, rendezvous })
+    }
+
+    let mut swarm = match args.transport {
+        Transport::Tcp => libp2p::SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(tcp::Config::default(), noise::Config::new, || yamux::Config::default())?
+            .with_behaviour(build_behaviour)?
+            .build(),
+        Transport::Quic => libp2p::SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_quic()
+            .with_behaviour(build_behaviour)?
+            .build(),
+    };
+
+    let local_peer_id = *swarm.local_peer_id();
+    info!(" Rendezvous Broker Peer ID: {}", local_peer_id);
+
+    let listen_addr: Multiaddr = match args.transport {
+        Transport::Tcp => format!("/ip4/0.0.0.0/tcp/{}", port).parse()?,
+        Transport::Quic => format!("/ip4/0.0.0.0/udp/{}/quic-v1", port).parse()?,
+    };
+    swarm.listen_on(listen_addr)?;
+
+    info!("⏳ Waiting for rendezvous registrations and discovery requests...");
+
+    loop {
+        tokio::select! {
+            event = swarm.select_next_some() => {
+                match event {
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Identify(event)) => {
+                        info!(" Identify event: {:?}", event);
+                    }
+                    SwarmEvent::Behaviour(MyBehaviourEvent::Rendezvous(event)) => {
+                        info!(" Rendezvous server event: {:?}", event);
+                    }
+                    SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                        info!(" Peer connected: {} via {}", peer_id, endpoint.get_remote_address());
+                    }
+                    SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+                        info!(" Peer disconnected: {} ({:?})", peer_id, cause);
+                    }
+                    SwarmEvent::NewListenAddr { address, .. } => {
+                        info!(" Listening on {}", address);
+
+                        let addr_string = address.to_string();
+                        let addr_parts: Vec<&str> = addr_string.split('/').collect();
+                        if addr_parts.len() >= 3 {
+                            let hint = match args.transport {
+                                Transport::Tcp => format!("/ip4/{}/tcp/{}/p2p/{}", addr_parts[2], port, local_peer_id),
+                                Transport::Quic => format!("/ip4/{}/udp/{}/quic-v1/p2p/{}", addr_parts[2], port, local_peer_id),
+                            };
+                            info!(" Connection address (pass to --rendezvous): {}", hint);
+                        }
+                    }
+                    SwarmEvent::IncomingConnection { .. } => {
+                        info!(" Incoming connection...");
+                    }
+                    _ => {}
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!(" Shutting down rendezvous broker...");
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}